@@ -0,0 +1,99 @@
+// proc-macro companion to the `AutoStruct` trait in the main crate: generates
+// `generate()` so callers stop hand-writing `get_field_i64("x")?.unwrap()` chains
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, GenericArgument, Lit, Meta, NestedMeta, PathArguments, Type};
+use syn::spanned::Spanned;
+
+#[proc_macro_derive(AutoStruct, attributes(fgb))]
+pub fn derive_auto_struct(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	let name = &input.ident;
+
+	let fields = match &input.data {
+		Data::Struct(data) => match &data.fields {
+			Fields::Named(fields) => &fields.named,
+			_ => panic!("AutoStruct can only be derived for structs with named fields"),
+		},
+		_ => panic!("AutoStruct can only be derived for structs"),
+	};
+
+	let field_inits = fields.iter().map(|field| {
+		let field_ident = field.ident.as_ref().expect("named field");
+		let column_name = rename_of(field).unwrap_or_else(|| field_ident.to_string());
+		let (inner_ty, optional) = unwrap_option(&field.ty);
+		let getter = getter_for(inner_ty);
+
+		if optional {
+			quote! { #field_ident: reader.#getter(#column_name)? }
+		} else {
+			quote! {
+				#field_ident: reader.#getter(#column_name)?
+					.ok_or_else(|| -> Box<dyn std::error::Error> { format!("missing required field {:?}", #column_name).into() })?
+			}
+		}
+	});
+
+	let expanded = quote! {
+		impl<'a> AutoStruct<'a> for #name {
+			fn generate<F: FeatureReader>(reader: &F) -> Result<Self, Box<dyn std::error::Error>> {
+				Ok(Self {
+					#(#field_inits),*
+				})
+			}
+		}
+	};
+
+	expanded.into()
+}
+
+// `#[fgb(rename = "...")]` overrides the column name a field is read from
+fn rename_of(field: &syn::Field) -> Option<String> {
+	for attr in &field.attrs {
+		if !attr.path.is_ident("fgb") { continue; }
+		let meta = attr.parse_meta().expect("malformed #[fgb(...)] attribute");
+		if let Meta::List(list) = meta {
+			for nested in list.nested {
+				if let NestedMeta::Meta(Meta::NameValue(nv)) = nested {
+					if nv.path.is_ident("rename") {
+						if let Lit::Str(s) = nv.lit {
+							return Some(s.value());
+						}
+					}
+				}
+			}
+		}
+	}
+	None
+}
+
+// `Option<T>` fields are nullable (left `None` when the column is absent);
+// everything else must be present or `generate` errors out
+fn unwrap_option(ty: &Type) -> (&Type, bool) {
+	if let Type::Path(type_path) = ty {
+		let segment = type_path.path.segments.last().expect("non-empty type path");
+		if segment.ident == "Option" {
+			if let PathArguments::AngleBracketed(args) = &segment.arguments {
+				if let Some(GenericArgument::Type(inner)) = args.args.first() {
+					return (inner, true);
+				}
+			}
+		}
+	}
+	(ty, false)
+}
+
+// maps a field's Rust type to the matching FeatureReader getter
+fn getter_for(ty: &Type) -> syn::Ident {
+	let type_name = match ty {
+		Type::Path(type_path) => type_path.path.segments.last().expect("non-empty type path").ident.to_string(),
+		_ => panic!("unsupported field type for AutoStruct"),
+	};
+	let getter = match type_name.as_str() {
+		"i32" => "get_field_i32",
+		"i64" => "get_field_i64",
+		"Point" => "get_field_point",
+		other => panic!("no FeatureReader getter known for field type {:?}", other),
+	};
+	syn::Ident::new(getter, ty.span())
+}
@@ -1,10 +1,14 @@
-use std::{fs::{File}, marker::PhantomData, error::Error};
+use std::{fs::{File}, io::{BufReader, BufWriter, BufRead, Seek, SeekFrom, Write}, marker::PhantomData, error::Error};
 use regex::Regex;
 
-use geo::{Geometry, Point};
+use geo::{Centroid, Geometry, HaversineDistance, LineString, Point, Polygon};
 use geozero::ToGeo;
-use flatgeobuf::{FgbReader, FallibleStreamingIterator, reader_state::FeaturesSelectedSeek, FeatureProperties};
-use gdal::{Dataset, vector::{OwnedFeatureIterator, Feature as GdalFeature, FieldValue}};
+use flatgeobuf::{FgbReader, HttpFgbReader, FallibleStreamingIterator, reader_state::{FeaturesSelectedSeek, FeaturesSelected}, FeatureProperties, GeometryType as FgbGeometryType};
+use gdal::{Dataset, vector::{LayerAccess, OwnedLayer, OwnedFeatureIterator, Feature as GdalFeature, FieldValue}};
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{Deserializer as JsonDeserializer, Map as JsonMap, Value as JsonValue};
+use autostruct_derive::AutoStruct;
 
 // this is the more general struct that tries opening the file
 // some crates make a stack of borrowing structs, so we'll need at least 2 layers
@@ -18,6 +22,32 @@ trait FormatDriver {
 	// create a reader (ideally this should look like for loop, but not right now)
 	type Layer: FeatureReader;
 	fn iter(&mut self) -> Result<Self::Layer, Box<dyn Error>>;
+	// same as `iter`, but only yields features that fall in the query rectangle.
+	// FGB and GPKG push the filter down to their spatial index instead of reading
+	// everything and testing in Rust; GeoJSON has no index, so it streams every
+	// feature and keeps those whose anchor point (the point itself, or the
+	// centroid of any other geometry) lies inside the rectangle
+	fn select_bbox(&mut self, min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> Result<Self::Layer, Box<dyn Error>>;
+	// yields features ordered by increasing great-circle distance from `origin`,
+	// for "N closest places" queries
+	fn sort_by_distance(&mut self, origin: Point) -> Result<Self::Layer, Box<dyn Error>>;
+}
+
+// parses an origin the way a geo sort expression does: "lat,long", split on the
+// first comma, both halves trimmed and parsed as f64
+fn parse_origin(origin: &str) -> Result<Point, Box<dyn Error>> {
+	let (lat, long) = origin.split_once(',').ok_or_else(|| format!("origin {:?} is not \"lat,long\"", origin))?;
+	let lat: f64 = lat.trim().parse().map_err(|_| format!("invalid latitude {:?}", lat.trim()))?;
+	let long: f64 = long.trim().parse().map_err(|_| format!("invalid longitude {:?}", long.trim()))?;
+	Ok(Point::new(long, lat))
+}
+
+// a point to measure distance from, for geometries that aren't already a Point
+fn geometry_anchor(geometry: &Geometry) -> Option<Point> {
+	match geometry {
+		Geometry::Point(p) => Some(*p),
+		g => g.centroid(),
+	}
 }
 
 trait FeatureReader {
@@ -27,9 +57,57 @@ trait FeatureReader {
 	fn get_field_i32(&self, field_name: &str) -> Result<Option<i32>, Box<dyn Error>>;
 	fn get_field_i64(&self, field_name: &str) -> Result<Option<i64>, Box<dyn Error>>;
 	fn get_field_point(&self, field_name: &str) -> Result<Option<Point>, Box<dyn Error>>;
+	// the full geometry, for layers that aren't just points (LineString, Polygon,
+	// MultiPolygon, GeometryCollection, ...)
+	fn get_field_geometry(&self, field_name: &str) -> Result<Option<Geometry>, Box<dyn Error>>;
+	// typed convenience getters built on top of get_field_geometry; a type
+	// mismatch is a descriptive error rather than a panic
+	fn get_field_linestring(&self, field_name: &str) -> Result<Option<LineString>, Box<dyn Error>> {
+		match self.get_field_geometry(field_name)? {
+			Some(Geometry::LineString(g)) => Ok(Some(g)),
+			Some(g) => Err(format!("expected a LineString in field {:?}, got a {}", field_name, geometry_kind(&g)).into()),
+			None => Ok(None),
+		}
+	}
+	fn get_field_polygon(&self, field_name: &str) -> Result<Option<Polygon>, Box<dyn Error>> {
+		match self.get_field_geometry(field_name)? {
+			Some(Geometry::Polygon(g)) => Ok(Some(g)),
+			Some(g) => Err(format!("expected a Polygon in field {:?}, got a {}", field_name, geometry_kind(&g)).into()),
+			None => Ok(None),
+		}
+	}
+}
+
+// human-readable name of a geometry's variant, for type-mismatch error messages
+fn geometry_kind(geometry: &Geometry) -> &'static str {
+	match geometry {
+		Geometry::Point(_) => "Point",
+		Geometry::Line(_) => "Line",
+		Geometry::LineString(_) => "LineString",
+		Geometry::Polygon(_) => "Polygon",
+		Geometry::MultiPoint(_) => "MultiPoint",
+		Geometry::MultiLineString(_) => "MultiLineString",
+		Geometry::MultiPolygon(_) => "MultiPolygon",
+		Geometry::GeometryCollection(_) => "GeometryCollection",
+		Geometry::Rect(_) => "Rect",
+		Geometry::Triangle(_) => "Triangle",
+	}
 }
 
 
+// symmetric write side of FeatureReader, so a pipeline can read from one driver
+// and write to another: build up one feature's fields/geometry, then `end_feature`
+// before moving on to the next
+trait FeatureWriter {
+	fn to_path(path: &str) -> Result<Self, Box<dyn Error>> where Self: Sized;
+	fn write_field_i32(&mut self, field_name: &str, value: i32) -> Result<(), Box<dyn Error>>;
+	fn write_field_i64(&mut self, field_name: &str, value: i64) -> Result<(), Box<dyn Error>>;
+	fn write_geometry(&mut self, geometry: &Point) -> Result<(), Box<dyn Error>>;
+	fn end_feature(&mut self) -> Result<(), Box<dyn Error>>;
+	// close out the FeatureCollection; the output is truncated JSON if this is skipped
+	fn finish(&mut self) -> Result<(), Box<dyn Error>>;
+}
+
 // this should have some code to work with the drivers, like `from_driver` below
 trait AutoStruct<'a> {
 	fn generate<F: FeatureReader>(reader: &F) -> Result<Self, Box<dyn Error>> where Self: Sized;
@@ -37,12 +115,33 @@ trait AutoStruct<'a> {
 
 // FORMAT DRIVER 1: GPKG (via GDAL)
 struct GpkgDriver<'a> {
-	fi: OwnedFeatureIterator,
+	// held back until `iter`/`select_bbox` so a spatial filter can still be
+	// applied before the layer is converted into a (non-resettable) feature iterator
+	layer: Option<OwnedLayer>,
+	// kept so `sort_by_distance` can reopen the layer with a wider spatial filter
+	// without disturbing `self.layer` (GDAL has no way to reset a filter in place)
+	file_path: String,
+	// the `:layer_name` suffix, if the path specified one; `None` means "the
+	// file's only layer" (from_path already rejects multi-layer files without one)
+	layer_name: Option<String>,
 	p: PhantomData<&'a bool>
 }
 
 const PATH_REGEXP:&str = r"^(?P<file_path>(?:.*/)?(?P<file_name>(?:.*/)?(?P<file_own_name>.*)\.(?P<extension>gpkg)))(?::(?P<layer_name>[a-z0-9_-]+))?$";
 
+impl<'a> GpkgDriver<'a> {
+	// reopens the dataset from scratch so `sort_by_distance` can widen its
+	// spatial filter on each pass (GDAL has no way to reset a filter already
+	// applied to an `OwnedLayer` that's been consumed into a feature iterator)
+	fn reopen_layer(&self) -> Result<OwnedLayer, Box<dyn Error>> {
+		let dataset = Dataset::open(&self.file_path)?;
+		match &self.layer_name {
+			Some(name) => Ok(dataset.into_layer_by_name(name)?),
+			None => Ok(dataset.into_layer(0)?),
+		}
+	}
+}
+
 impl<'a> FormatDriver for GpkgDriver<'a> {
 	type Layer = GpkgLayer<'a>;
 	fn can_open(path: &str) -> bool {
@@ -51,69 +150,181 @@ impl<'a> FormatDriver for GpkgDriver<'a> {
 	}
 
 	fn from_path(path: &str) -> Result<Self, Box<dyn Error>> {
-		let dataset = Dataset::open(path)?;
-		// TODO: choose layer from path expression or return error if can't choose
-		let layer = dataset.into_layer(0)?;
-		let fi = layer.owned_features();
-		Ok(Self { fi, p: PhantomData })
+		let re = Regex::new(PATH_REGEXP).unwrap();
+		let caps = re.captures(path).ok_or_else(|| format!("not a valid gpkg path: {:?}", path))?;
+		let file_path = &caps["file_path"];
+		let dataset = Dataset::open(file_path)?;
+
+		let layer_name = caps.name("layer_name").map(|m| m.as_str().to_string());
+		let layer = match &layer_name {
+			Some(name) => dataset.into_layer_by_name(name)?,
+			None => {
+				if dataset.layer_count() > 1 {
+					let layer_names: Vec<String> = (0..dataset.layer_count())
+						.map(|i| Ok(dataset.layer(i)?.name()))
+						.collect::<Result<_, Box<dyn Error>>>()?;
+					return Err(format!(
+						"{:?} has multiple layers ({}); open one with a \":layer\" suffix, e.g. {:?}",
+						file_path, layer_names.join(", "), format!("{}:{}", file_path, layer_names[0])
+					).into());
+				}
+				dataset.into_layer(0)?
+			}
+		};
+		Ok(Self { layer: Some(layer), file_path: file_path.to_string(), layer_name, p: PhantomData })
 	}
 
 	fn iter(&mut self) -> Result<Self::Layer, Box<dyn Error>> {
-		let fii = self.fi.into_iter();
-		Ok(GpkgLayer { fii, feature: None })
+		let layer = self.layer.take().expect("driver already consumed");
+		let fii = layer.owned_features();
+		Ok(GpkgLayer::Streaming(GpkgStreamingLayer { fii, feature: None }))
+	}
+
+	fn select_bbox(&mut self, min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> Result<Self::Layer, Box<dyn Error>> {
+		let mut layer = self.layer.take().expect("driver already consumed");
+		layer.set_spatial_filter_rect(min_x, min_y, max_x, max_y);
+		let fii = layer.owned_features();
+		Ok(GpkgLayer::Streaming(GpkgStreamingLayer { fii, feature: None }))
+	}
+
+	fn sort_by_distance(&mut self, origin: Point) -> Result<Self::Layer, Box<dyn Error>> {
+		// GDAL has no equivalent of FGB's packed R-tree to push the ordering into,
+		// so mirror the FGB expanding-ring search instead: apply a spatial filter
+		// rectangle around `origin`, widening it until enough candidates turn up,
+		// then sort that (still spatially-filtered) candidate set in memory
+		let mut half_width = NEAREST_INITIAL_HALF_WIDTH_DEG;
+		let mut records = Vec::new();
+		loop {
+			let mut layer = self.reopen_layer()?;
+			layer.set_spatial_filter_rect(
+				origin.x() - half_width, origin.y() - half_width,
+				origin.x() + half_width, origin.y() + half_width,
+			);
+			records.clear();
+			for feature in layer.owned_features() {
+				let geometry = feature.geometry().to_geo()?;
+				let distance = match geometry_anchor(&geometry) {
+					Some(anchor) => origin.haversine_distance(&anchor),
+					None => continue,
+				};
+				let mut properties = std::collections::HashMap::new();
+				for (name, value) in feature.fields() {
+					if let Some(v) = value { properties.insert(name, v); }
+				}
+				records.push(GpkgRecord { properties, geometry, distance });
+			}
+			if records.len() >= NEAREST_MIN_CANDIDATES || half_width >= 180.0 { break; }
+			half_width *= 2.0;
+		}
+		records.sort_by(|a, b| a.distance.partial_cmp(&b.distance).unwrap());
+		Ok(GpkgLayer::ByDistance(records, None))
 	}
 }
 
-struct GpkgLayer<'a> {
-	fii: &'a mut OwnedFeatureIterator,
+struct GpkgStreamingLayer<'a> {
+	fii: OwnedFeatureIterator,
 	feature: Option<GdalFeature<'a>>,
 }
 
+struct GpkgRecord {
+	// keeps the GDAL `FieldValue` as-is (not narrowed to i64) so a `ByDistance`
+	// read of a non-integer field can panic with the same "wrong format" message
+	// a `Streaming` read would, instead of silently looking "missing"
+	properties: std::collections::HashMap<String, FieldValue>,
+	geometry: Geometry,
+	distance: f64,
+}
+
+// either streaming straight off the GDAL feature iterator, or a candidate set
+// already materialized and sorted in memory (by sort_by_distance)
+enum GpkgLayer<'a> {
+	Streaming(GpkgStreamingLayer<'a>),
+	ByDistance(Vec<GpkgRecord>, Option<usize>),
+}
+
 impl<'a> FeatureReader for GpkgLayer<'a> {
 	fn forward(&mut self) -> Result<bool, Box<dyn Error>> {
-		if let Some(f) = self.fii.next() {
-			self.feature.replace(f);
-			Ok(true)
+		match self {
+			GpkgLayer::Streaming(layer) => {
+				if let Some(f) = Iterator::next(&mut layer.fii) {
+					layer.feature.replace(f);
+					Ok(true)
+				} else { Ok(false) }
+			}
+			GpkgLayer::ByDistance(records, index) => {
+				let next_index = index.map_or(0, |i| i + 1);
+				if next_index >= records.len() { return Ok(false); }
+				*index = Some(next_index);
+				Ok(true)
+			}
 		}
-		else { Ok(false) }
 	}
+
 	fn get_field_i32(&self, field_name: &str) -> Result<Option<i32>, Box<dyn Error>> {
-		match match match &self.feature {
-			Some(f) => f.field(field_name)?,
-			None => panic!("no feature but reading field")
-		} {
-			Some(v) => v,
-			None => return Ok(None),
-		} {
-			FieldValue::IntegerValue(v) => Ok(Some(v.into())),
-			FieldValue::Integer64Value(v) => Ok(Some(v.try_into()?)),
-			_ => panic!("wrong format")
+		match self {
+			GpkgLayer::Streaming(layer) => match match match &layer.feature {
+				Some(f) => f.field(field_name)?,
+				None => panic!("no feature but reading field")
+			} {
+				Some(v) => v,
+				None => return Ok(None),
+			} {
+				FieldValue::IntegerValue(v) => Ok(Some(v.into())),
+				FieldValue::Integer64Value(v) => Ok(Some(v.try_into()?)),
+				_ => panic!("wrong format")
+			},
+			GpkgLayer::ByDistance(..) => match self.get_field_i64(field_name)? {
+				Some(v) => Ok(Some(v.try_into()?)),
+				None => Ok(None),
+			},
 		}
 	}
 	fn get_field_i64(&self, field_name: &str) -> Result<Option<i64>, Box<dyn Error>> {
-		match match match &self.feature {
-			Some(f) => f.field(field_name)?,
-			None => panic!("no feature but reading field")
-		} {
-			Some(v) => v,
-			None => return Ok(None),
-		} {
-			FieldValue::IntegerValue(v) => Ok(Some(v.into())),
-			FieldValue::Integer64Value(v) => Ok(Some(v.try_into()?)),
-			_ => panic!("wrong format")
-		}
-	}
-
-	fn get_field_point(&self, _field_name: &str) -> Result<Option<Point>, Box<dyn Error>> {
-		match match &self.feature {
-			Some(f) => Some(f.geometry().to_geo()?),
-			None => panic!("no feature read yet"),
-			_ => None::<Geometry> // TODO: this is just to fix the non-exhaustive patterns
-		} {
+		match self {
+			GpkgLayer::Streaming(layer) => match match match &layer.feature {
+				Some(f) => f.field(field_name)?,
+				None => panic!("no feature but reading field")
+			} {
+				Some(v) => v,
+				None => return Ok(None),
+			} {
+				FieldValue::IntegerValue(v) => Ok(Some(v.into())),
+				FieldValue::Integer64Value(v) => Ok(Some(v.try_into()?)),
+				_ => panic!("wrong format")
+			},
+			// same IntegerValue/Integer64Value-or-panic shape as the Streaming arm
+			// above, so a `ByDistance` read of a non-integer field fails the same
+			// way a `Streaming` read of it would, rather than looking "missing"
+			GpkgLayer::ByDistance(records, index) => {
+				let record = &records[index.expect("no feature read yet")];
+				match record.properties.get(field_name) {
+					Some(FieldValue::IntegerValue(v)) => Ok(Some((*v).into())),
+					Some(FieldValue::Integer64Value(v)) => Ok(Some(*v)),
+					Some(_) => panic!("wrong format"),
+					None => Ok(None),
+				}
+			}
+		}
+	}
+
+	fn get_field_point(&self, field_name: &str) -> Result<Option<Point>, Box<dyn Error>> {
+		match self.get_field_geometry(field_name)? {
 			Some(Geometry::Point(g)) => Ok(Some(g)),
-			// just to fix the return types/exhaustiveness
+			Some(g) => Err(format!("expected a Point in field {:?}, got a {}", field_name, geometry_kind(&g)).into()),
 			None => Ok(None),
-			_ => panic!("what have I just got?")
+		}
+	}
+
+	fn get_field_geometry(&self, _field_name: &str) -> Result<Option<Geometry>, Box<dyn Error>> {
+		match self {
+			GpkgLayer::Streaming(layer) => match &layer.feature {
+				Some(f) => Ok(Some(f.geometry().to_geo()?)),
+				None => panic!("no feature read yet"),
+			},
+			GpkgLayer::ByDistance(records, index) => {
+				let record = &records[index.expect("no feature read yet")];
+				Ok(Some(record.geometry.clone()))
+			}
 		}
 	}
 }
@@ -123,9 +334,18 @@ impl<'a> FeatureReader for GpkgLayer<'a> {
 // so I must either a) open the file outside, or b) have 2 structs
 struct FgbDriver<'a> {
 	fp: File,
+	geometry_type: FgbGeometryType,
 	p: PhantomData<&'a bool>
 }
 
+impl<'a> FgbDriver<'a> {
+	// lets callers learn a layer's geometry type, straight from the FGB header,
+	// before choosing which typed getter to call while iterating
+	fn geometry_type(&self) -> FgbGeometryType {
+		self.geometry_type
+	}
+}
+
 impl<'a> FormatDriver for FgbDriver<'a> {
 	type Layer = FgbFeatureReader<'a>;
 	fn can_open(path: &str) -> bool {
@@ -133,24 +353,204 @@ impl<'a> FormatDriver for FgbDriver<'a> {
 	}
 
 	fn from_path(path: &str) -> Result<Self, Box<dyn Error>> {
-		let fp = File::open(path)?;
-		Ok(Self { fp, p: PhantomData })
+		let mut fp = File::open(path)?;
+		let geometry_type = FgbReader::open(&mut fp)?.header().geometry_type();
+		fp.seek(SeekFrom::Start(0))?;
+		Ok(Self { fp, geometry_type, p: PhantomData })
 	}
 
 	fn iter(&mut self) -> Result<Self::Layer, Box<dyn Error>> {
 		let features_selected = FgbReader::open(&mut self.fp)?.select_all()?;
-		Ok(FgbFeatureReader { features_selected })
+		Ok(FgbFeatureReader::Streaming(features_selected))
 	}
+
+	fn select_bbox(&mut self, min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> Result<Self::Layer, Box<dyn Error>> {
+		// walks the packed Hilbert R-tree: test each node's bbox against the query
+		// box, descend only into intersecting children, and seek straight to the
+		// matching features instead of scanning the whole file linearly
+		let features_selected = FgbReader::open(&mut self.fp)?.select_bbox(min_x, min_y, max_x, max_y)?;
+		Ok(FgbFeatureReader::Streaming(features_selected))
+	}
+
+	fn sort_by_distance(&mut self, origin: Point) -> Result<Self::Layer, Box<dyn Error>> {
+		// expanding ring search over the packed R-tree: start with a small bbox
+		// around the origin, pull candidates, and grow the window until there are
+		// enough of them to be confident nothing closer was missed outside the box
+		let mut half_width = NEAREST_INITIAL_HALF_WIDTH_DEG;
+		let mut records = Vec::new();
+		loop {
+			self.fp.seek(SeekFrom::Start(0))?;
+			let reader = FgbReader::open(&mut self.fp)?;
+			// `columns()` is `None` for a headerless/columnless layer, not an
+			// empty vector, so there's nothing to `.iter()` over in that case
+			let columns: Vec<String> = reader.header().columns()
+				.map(|cols| cols.iter().map(|c| c.name().to_string()).collect())
+				.unwrap_or_default();
+			let mut selected = reader.select_bbox(
+				origin.x() - half_width, origin.y() - half_width,
+				origin.x() + half_width, origin.y() + half_width,
+			)?;
+			records.clear();
+			while selected.next()?.is_some() {
+				let ft = selected.cur_feature();
+				let geometry = ft.to_geo()?;
+				let distance = match geometry_anchor(&geometry) {
+					Some(anchor) => origin.haversine_distance(&anchor),
+					None => continue,
+				};
+				let mut properties = std::collections::HashMap::new();
+				let mut other_fields = std::collections::HashSet::new();
+				for name in &columns {
+					match ft.property::<i64>(name) {
+						Ok(v) => { properties.insert(name.clone(), v); }
+						Err(_) => { other_fields.insert(name.clone()); }
+					}
+				}
+				records.push(FgbRecord { properties, other_fields, geometry, distance });
+			}
+			if records.len() >= NEAREST_MIN_CANDIDATES || half_width >= 180.0 { break; }
+			half_width *= 2.0;
+		}
+		records.sort_by(|a, b| a.distance.partial_cmp(&b.distance).unwrap());
+		Ok(FgbFeatureReader::ByDistance(records, None))
+	}
+}
+
+// how many candidates an expanding-window nearest search tries to gather before
+// trusting that the closest ones in the buffer really are the closest overall
+const NEAREST_MIN_CANDIDATES: usize = 16;
+const NEAREST_INITIAL_HALF_WIDTH_DEG: f64 = 0.01;
+
+struct FgbRecord {
+	properties: std::collections::HashMap<String, i64>,
+	// columns that exist in the header but couldn't be captured as i64 (wrong
+	// type, or no value for this feature) - read through `Streaming` instead of
+	// treating these the same as a field that isn't in the schema at all
+	other_fields: std::collections::HashSet<String>,
+	geometry: Geometry,
+	distance: f64,
 }
 
-struct FgbFeatureReader<'a> {
-	features_selected: FgbReader<'a, File, FeaturesSelectedSeek>,
+// either streaming straight off the FGB selection, or a candidate set already
+// materialized and sorted in memory (by sort_by_distance)
+enum FgbFeatureReader<'a> {
+	Streaming(FgbReader<'a, File, FeaturesSelectedSeek>),
+	ByDistance(Vec<FgbRecord>, Option<usize>),
 }
 
 impl<'a> FeatureReader for FgbFeatureReader<'a> {
 	fn forward(&mut self) -> Result<bool, Box<dyn Error>> {
-		// getters should use self.features_selected.get() to get current feature
-		Ok(self.features_selected.next()?.is_some())
+		match self {
+			// getters should use self.features_selected.get() to get current feature
+			FgbFeatureReader::Streaming(features_selected) => Ok(features_selected.next()?.is_some()),
+			FgbFeatureReader::ByDistance(records, index) => {
+				let next_index = index.map_or(0, |i| i + 1);
+				if next_index >= records.len() { return Ok(false); }
+				*index = Some(next_index);
+				Ok(true)
+			}
+		}
+	}
+	fn get_field_i32(&self, field_name: &str) -> Result<Option<i32>, Box<dyn Error>> {
+		match self {
+			FgbFeatureReader::Streaming(features_selected) => Ok(Some(features_selected.cur_feature().property::<i32>(field_name)?)),
+			FgbFeatureReader::ByDistance(..) => match self.get_field_i64(field_name)? {
+				Some(v) => Ok(Some(v.try_into()?)),
+				None => Ok(None),
+			},
+		}
+	}
+	fn get_field_i64(&self, field_name: &str) -> Result<Option<i64>, Box<dyn Error>> {
+		match self {
+			FgbFeatureReader::Streaming(features_selected) => Ok(Some(features_selected.cur_feature().property::<i64>(field_name)?)),
+			// mirrors the GpkgLayer::ByDistance arm above: a field that's in the
+			// schema but wasn't captured as i64 errors instead of reading as "missing"
+			FgbFeatureReader::ByDistance(records, index) => {
+				let record = &records[index.expect("no feature read yet")];
+				match record.properties.get(field_name) {
+					Some(v) => Ok(Some(*v)),
+					None if record.other_fields.contains(field_name) =>
+						Err(format!("field {:?} exists but isn't an integer; read it through a Streaming layer instead", field_name).into()),
+					None => Ok(None),
+				}
+			}
+		}
+	}
+	fn get_field_point(&self, field_name: &str) -> Result<Option<Point>, Box<dyn Error>> {
+		match self.get_field_geometry(field_name)? {
+			Some(Geometry::Point(p)) => Ok(Some(p)),
+			Some(g) => Err(format!("expected a Point in field {:?}, got a {}", field_name, geometry_kind(&g)).into()),
+			None => Ok(None),
+		}
+	}
+	fn get_field_geometry(&self, _field_name: &str) -> Result<Option<Geometry>, Box<dyn Error>> {
+		match self {
+			FgbFeatureReader::Streaming(features_selected) => Ok(Some(features_selected.cur_feature().to_geo()?)),
+			FgbFeatureReader::ByDistance(records, index) => {
+				let record = &records[index.expect("no feature read yet")];
+				Ok(Some(record.geometry.clone()))
+			}
+		}
+	}
+}
+
+// async mirror of FormatDriver/FeatureReader, for backends that read over the network
+// instead of a local, already-open file handle
+#[async_trait(?Send)]
+trait AsyncFormatDriver {
+	fn can_open(path: &str) -> bool where Self: Sized;
+	async fn from_url(url: &str) -> Result<Self, Box<dyn Error>>
+		where Self: Sized;
+	type Layer: AsyncFeatureReader;
+	async fn select_bbox(&mut self, min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> Result<Self::Layer, Box<dyn Error>>;
+}
+
+#[async_trait(?Send)]
+trait AsyncFeatureReader {
+	async fn forward(&mut self) -> Result<bool, Box<dyn Error>>;
+	// same accessors as FeatureReader
+	fn get_field_i32(&self, field_name: &str) -> Result<Option<i32>, Box<dyn Error>>;
+	fn get_field_i64(&self, field_name: &str) -> Result<Option<i64>, Box<dyn Error>>;
+	fn get_field_point(&self, field_name: &str) -> Result<Option<Point>, Box<dyn Error>>;
+	// the full geometry, for layers that aren't just points
+	fn get_field_geometry(&self, field_name: &str) -> Result<Option<Geometry>, Box<dyn Error>>;
+}
+
+// FORMAT DRIVER 3: FGB over HTTP(S) (range requests, no local file)
+struct HttpFgbDriver {
+	url: String,
+}
+
+#[async_trait(?Send)]
+impl AsyncFormatDriver for HttpFgbDriver {
+	type Layer = HttpFgbFeatureReader;
+
+	fn can_open(path: &str) -> bool {
+		(path.starts_with("http://") || path.starts_with("https://")) && path.ends_with(".fgb")
+	}
+
+	async fn from_url(url: &str) -> Result<Self, Box<dyn Error>> {
+		Ok(Self { url: url.to_string() })
+	}
+
+	async fn select_bbox(&mut self, min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> Result<Self::Layer, Box<dyn Error>> {
+		// HttpFgbReader::open fetches just the header + packed R-tree index bytes;
+		// select_bbox then issues range requests for only the matching features'
+		// byte ranges instead of streaming the whole dataset
+		let reader = HttpFgbReader::open(&self.url).await?;
+		let features_selected = reader.select_bbox(min_x, min_y, max_x, max_y).await?;
+		Ok(HttpFgbFeatureReader { features_selected })
+	}
+}
+
+struct HttpFgbFeatureReader {
+	features_selected: HttpFgbReader<FeaturesSelected>,
+}
+
+#[async_trait(?Send)]
+impl AsyncFeatureReader for HttpFgbFeatureReader {
+	async fn forward(&mut self) -> Result<bool, Box<dyn Error>> {
+		Ok(self.features_selected.next().await?.is_some())
 	}
 	fn get_field_i32(&self, field_name: &str) -> Result<Option<i32>, Box<dyn Error>> {
 		let ft = self.features_selected.cur_feature();
@@ -160,13 +560,298 @@ impl<'a> FeatureReader for FgbFeatureReader<'a> {
 		let ft = self.features_selected.cur_feature();
 		Ok(Some(ft.property::<i64>(field_name)?))
 	}
-	fn get_field_point(&self, _field_name: &str) -> Result<Option<Point>, Box<dyn Error>> {
+	fn get_field_point(&self, field_name: &str) -> Result<Option<Point>, Box<dyn Error>> {
+		match self.get_field_geometry(field_name)? {
+			Some(Geometry::Point(p)) => Ok(Some(p)),
+			Some(g) => Err(format!("expected a Point in field {:?}, got a {}", field_name, geometry_kind(&g)).into()),
+			None => Ok(None),
+		}
+	}
+	fn get_field_geometry(&self, _field_name: &str) -> Result<Option<Geometry>, Box<dyn Error>> {
 		let ft = self.features_selected.cur_feature();
-		match ft.to_geo()? {
-			Geometry::Point(p) => Ok(Some(p)),
-			_ => panic!("wrong geometry type!")
+		Ok(Some(ft.to_geo()?))
+	}
+}
+
+// FORMAT DRIVER 4: streaming GeoJSON
+// reads/writes one feature at a time instead of (de)serializing the whole
+// FeatureCollection into memory
+struct GeoJsonDriver<'a> {
+	fp: File,
+	p: PhantomData<&'a bool>
+}
+
+#[derive(Debug, Deserialize)]
+struct GeoJsonFeature {
+	properties: JsonMap<String, JsonValue>,
+	geometry: JsonValue,
+}
+
+impl<'a> FormatDriver for GeoJsonDriver<'a> {
+	type Layer = GeoJsonFeatureReader<'a>;
+
+	fn can_open(path: &str) -> bool {
+		path.ends_with(".geojson") || path.ends_with(".json")
+	}
+
+	fn from_path(path: &str) -> Result<Self, Box<dyn Error>> {
+		let fp = File::open(path)?;
+		Ok(Self { fp, p: PhantomData })
+	}
+
+	fn iter(&mut self) -> Result<Self::Layer, Box<dyn Error>> {
+		let mut reader = BufReader::new(&self.fp);
+		skip_to_features_array(&mut reader)?;
+		Ok(GeoJsonFeatureReader::Streaming(GeoJsonStreamingLayer { reader, feature: None, bbox: None }))
+	}
+
+	fn select_bbox(&mut self, min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> Result<Self::Layer, Box<dyn Error>> {
+		// no index to push the filter into (unlike FGB's R-tree or GPKG's layer
+		// filter), so still stream feature-by-feature and test each one in Rust
+		let mut reader = BufReader::new(&self.fp);
+		skip_to_features_array(&mut reader)?;
+		Ok(GeoJsonFeatureReader::Streaming(GeoJsonStreamingLayer { reader, feature: None, bbox: Some((min_x, min_y, max_x, max_y)) }))
+	}
+
+	fn sort_by_distance(&mut self, origin: Point) -> Result<Self::Layer, Box<dyn Error>> {
+		// no spatial index at all for this backend, so just stream every feature
+		// once and sort the whole candidate set in memory
+		let mut reader = BufReader::new(&self.fp);
+		skip_to_features_array(&mut reader)?;
+		let mut records = Vec::new();
+		while !at_end_of_features_array(&mut reader)? {
+			let feature: GeoJsonFeature = GeoJsonFeature::deserialize(&mut JsonDeserializer::from_reader(&mut reader))?;
+			let geometry = geometry_value_to_geo(&feature.geometry)?;
+			let distance = match geometry.as_ref().and_then(geometry_anchor) {
+				Some(anchor) => origin.haversine_distance(&anchor),
+				None => continue,
+			};
+			records.push((feature, distance));
+		}
+		records.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+		Ok(GeoJsonFeatureReader::ByDistance(records, None))
+	}
+}
+
+// advances past everything up to and including the `"features":[` that opens
+// the feature array, so the reader is left positioned at the first feature (or `]`)
+fn skip_to_features_array(reader: &mut impl BufRead) -> Result<(), Box<dyn Error>> {
+	let needle = b"\"features\"";
+	let mut matched = 0;
+	let mut byte = [0u8; 1];
+	loop {
+		if reader.read(&mut byte)? == 0 {
+			return Err("unexpected end of file before \"features\" array".into());
+		}
+		if byte[0] == needle[matched] {
+			matched += 1;
+			if matched == needle.len() { break; }
+		} else {
+			matched = if byte[0] == needle[0] { 1 } else { 0 };
+		}
+	}
+	skip_until_byte(reader, b':')?;
+	skip_until_byte(reader, b'[')?;
+	Ok(())
+}
+
+fn skip_until_byte(reader: &mut impl BufRead, target: u8) -> Result<(), Box<dyn Error>> {
+	let mut byte = [0u8; 1];
+	loop {
+		if reader.read(&mut byte)? == 0 {
+			return Err(format!("unexpected end of file while looking for {:?}", target as char).into());
+		}
+		if byte[0] == target { return Ok(()); }
+	}
+}
+
+// skips whitespace and the comma between two features; returns true if the next
+// non-whitespace byte closes the array (i.e. there are no more features)
+fn at_end_of_features_array(reader: &mut impl BufRead) -> Result<bool, Box<dyn Error>> {
+	loop {
+		let peek = match reader.fill_buf()?.first() {
+			Some(b) => *b,
+			None => return Ok(true),
+		};
+		match peek {
+			b' ' | b'\t' | b'\n' | b'\r' | b',' => { reader.consume(1); }
+			b']' => return Ok(true),
+			_ => return Ok(false),
+		}
+	}
+}
+
+struct GeoJsonStreamingLayer<'a> {
+	reader: BufReader<&'a File>,
+	feature: Option<GeoJsonFeature>,
+	bbox: Option<(f64, f64, f64, f64)>,
+}
+
+fn point_in_bbox(point: &Point, bbox: (f64, f64, f64, f64)) -> bool {
+	let (min_x, min_y, max_x, max_y) = bbox;
+	point.x() >= min_x && point.x() <= max_x && point.y() >= min_y && point.y() <= max_y
+}
+
+// either streaming straight off the file, or a candidate set already
+// materialized and sorted in memory (by sort_by_distance)
+enum GeoJsonFeatureReader<'a> {
+	Streaming(GeoJsonStreamingLayer<'a>),
+	ByDistance(Vec<(GeoJsonFeature, f64)>, Option<usize>),
+}
+
+impl<'a> FeatureReader for GeoJsonFeatureReader<'a> {
+	fn forward(&mut self) -> Result<bool, Box<dyn Error>> {
+		match self {
+			GeoJsonFeatureReader::Streaming(layer) => loop {
+				if at_end_of_features_array(&mut layer.reader)? {
+					layer.feature = None;
+					return Ok(false);
+				}
+				let feature: GeoJsonFeature = GeoJsonFeature::deserialize(&mut JsonDeserializer::from_reader(&mut layer.reader))?;
+				if let Some(bbox) = layer.bbox {
+					// test the bbox against an anchor point for any geometry, not just
+					// Point features, the same way sort_by_distance does - otherwise
+					// non-point geometries silently bypass the filter entirely
+					match geometry_value_to_geo(&feature.geometry)?.as_ref().and_then(geometry_anchor) {
+						Some(anchor) => if !point_in_bbox(&anchor, bbox) { continue; },
+						None => continue,
+					}
+				}
+				layer.feature = Some(feature);
+				return Ok(true);
+			},
+			GeoJsonFeatureReader::ByDistance(records, index) => {
+				let next_index = index.map_or(0, |i| i + 1);
+				if next_index >= records.len() { return Ok(false); }
+				*index = Some(next_index);
+				Ok(true)
+			}
+		}
+	}
+
+	fn get_field_i32(&self, field_name: &str) -> Result<Option<i32>, Box<dyn Error>> {
+		match self.current_feature()?.properties.get(field_name) {
+			Some(v) => Ok(Some(v.as_i64().ok_or("field is not an integer")?.try_into()?)),
+			None => Ok(None)
+		}
+	}
+
+	fn get_field_i64(&self, field_name: &str) -> Result<Option<i64>, Box<dyn Error>> {
+		match self.current_feature()?.properties.get(field_name) {
+			Some(v) => Ok(Some(v.as_i64().ok_or("field is not an integer")?)),
+			None => Ok(None)
+		}
+	}
+
+	fn get_field_point(&self, field_name: &str) -> Result<Option<Point>, Box<dyn Error>> {
+		match self.get_field_geometry(field_name)? {
+			Some(Geometry::Point(p)) => Ok(Some(p)),
+			Some(g) => Err(format!("expected a Point in field {:?}, got a {}", field_name, geometry_kind(&g)).into()),
+			None => Ok(None),
 		}
 	}
+
+	fn get_field_geometry(&self, _field_name: &str) -> Result<Option<Geometry>, Box<dyn Error>> {
+		geometry_value_to_geo(&self.current_feature()?.geometry)
+	}
+}
+
+impl<'a> GeoJsonFeatureReader<'a> {
+	fn current_feature(&self) -> Result<&GeoJsonFeature, Box<dyn Error>> {
+		match self {
+			GeoJsonFeatureReader::Streaming(layer) => match &layer.feature {
+				Some(f) => Ok(f),
+				None => panic!("no feature read yet"),
+			},
+			GeoJsonFeatureReader::ByDistance(records, index) => {
+				Ok(&records[index.expect("no feature read yet")].0)
+			}
+		}
+	}
+}
+
+fn geometry_value_to_geo(geometry: &JsonValue) -> Result<Option<Geometry>, Box<dyn Error>> {
+	let geom_type = geometry.get("type").and_then(JsonValue::as_str).ok_or("geometry is missing a \"type\"")?;
+	let coordinates = &geometry["coordinates"];
+	match geom_type {
+		"Point" => {
+			let (x, y) = parse_coord(coordinates)?;
+			Ok(Some(Geometry::Point(Point::new(x, y))))
+		}
+		"LineString" => Ok(Some(Geometry::LineString(LineString::from(parse_coord_list(coordinates)?)))),
+		"Polygon" => {
+			let mut rings = coordinates.as_array().ok_or("malformed Polygon geometry")?.iter();
+			let exterior = LineString::from(parse_coord_list(rings.next().ok_or("Polygon has no exterior ring")?)?);
+			let interiors = rings.map(|ring| Ok(LineString::from(parse_coord_list(ring)?)))
+				.collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+			Ok(Some(Geometry::Polygon(Polygon::new(exterior, interiors))))
+		}
+		other => Err(format!("unsupported GeoJSON geometry type {:?}", other).into())
+	}
+}
+
+fn parse_coord(value: &JsonValue) -> Result<(f64, f64), Box<dyn Error>> {
+	let coords = value.as_array().ok_or("malformed coordinates")?;
+	let x = coords.get(0).and_then(JsonValue::as_f64).ok_or("malformed coordinates")?;
+	let y = coords.get(1).and_then(JsonValue::as_f64).ok_or("malformed coordinates")?;
+	Ok((x, y))
+}
+
+fn parse_coord_list(value: &JsonValue) -> Result<Vec<(f64, f64)>, Box<dyn Error>> {
+	value.as_array().ok_or("malformed coordinates")?.iter().map(parse_coord).collect()
+}
+
+struct GeoJsonWriter {
+	w: BufWriter<File>,
+	wrote_any: bool,
+	properties: JsonMap<String, JsonValue>,
+	geometry: Option<JsonValue>,
+}
+
+impl FeatureWriter for GeoJsonWriter {
+	fn to_path(path: &str) -> Result<Self, Box<dyn Error>> {
+		let mut w = BufWriter::new(File::create(path)?);
+		write!(w, r#"{{"type":"FeatureCollection","features":["#)?;
+		Ok(Self { w, wrote_any: false, properties: JsonMap::new(), geometry: None })
+	}
+
+	fn write_field_i32(&mut self, field_name: &str, value: i32) -> Result<(), Box<dyn Error>> {
+		self.properties.insert(field_name.to_string(), JsonValue::from(value));
+		Ok(())
+	}
+
+	fn write_field_i64(&mut self, field_name: &str, value: i64) -> Result<(), Box<dyn Error>> {
+		self.properties.insert(field_name.to_string(), JsonValue::from(value));
+		Ok(())
+	}
+
+	fn write_geometry(&mut self, geometry: &Point) -> Result<(), Box<dyn Error>> {
+		self.geometry = Some(serde_json::json!({
+			"type": "Point",
+			"coordinates": [geometry.x(), geometry.y()]
+		}));
+		Ok(())
+	}
+
+	fn end_feature(&mut self) -> Result<(), Box<dyn Error>> {
+		if self.wrote_any {
+			write!(self.w, ",")?;
+		}
+		let feature = serde_json::json!({
+			"type": "Feature",
+			"properties": JsonValue::Object(std::mem::take(&mut self.properties)),
+			"geometry": self.geometry.take().ok_or("write_geometry must be called before end_feature")?,
+		});
+		serde_json::to_writer(&mut self.w, &feature)?;
+		self.wrote_any = true;
+		Ok(())
+	}
+
+	fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+		write!(self.w, "]}}")?;
+		self.w.flush()?;
+		Ok(())
+	}
 }
 
 struct BoxDriver<T>(T);
@@ -177,24 +862,20 @@ where T::Layer: 'static {
 	fn can_open(_: &str) -> bool { false }
 	fn from_path(path: &str) -> Result<Self, Box<dyn Error>> { todo!() }
 	fn iter(&mut self) -> Result<Self::Layer, Box<dyn Error>> { todo!() }
+	fn select_bbox(&mut self, min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> Result<Self::Layer, Box<dyn Error>> { todo!() }
+	fn sort_by_distance(&mut self, origin: Point) -> Result<Self::Layer, Box<dyn Error>> { todo!() }
 
 }
 
-#[derive(Debug)]
+// `generate()` below is written by the #[derive(AutoStruct)] macro: one
+// get_field_* call per field, picked by the field's Rust type, with Option<T>
+// fields left as None when the column is absent
+#[derive(Debug, AutoStruct)]
 struct MyStruct {
 	x: i64,
 	geometry: Point
 }
 
-impl<'a> AutoStruct<'a> for MyStruct {
-	fn generate<F: FeatureReader>(reader: &F) -> Result<Self, Box<dyn Error>> {
-		Ok(Self {
-			x: reader.get_field_i64("x")?.unwrap(),
-			geometry: reader.get_field_point("geometry")?.unwrap()
-		})
-	}
-}
-
 fn main() -> Result<(), Box<dyn Error>> {
 	let p = vec![
 		"places.gpkg:cities",
@@ -219,5 +900,222 @@ fn main() -> Result<(), Box<dyn Error>> {
 			continue;
 		};
 	}
+
+	// smoke-test bbox filtering end to end: the loop above only opens the
+	// drivers, so nothing has actually pushed a query rectangle down to a driver
+	if let Ok(mut gpkg) = GpkgDriver::from_path("places.gpkg:cities") {
+		let mut layer = gpkg.select_bbox(-74.1, 40.6, -73.9, 40.8)?;
+		while layer.forward()? {
+			if let Some(geometry) = layer.get_field_geometry("geometry")? {
+				println!("gpkg feature in bbox is a {}", geometry_kind(&geometry));
+			}
+		}
+	}
+
+	// exercise the FGB header's geometry type; expect() instead of `if let Ok`
+	// so a regression fails loudly instead of silently no-op'ing. Unlike the
+	// GeoJSON driver, there's no practical way to hand-author a binary FGB
+	// fixture for a #[test] without the flatgeobuf write API, so this needs a
+	// real places.fgb to actually run
+	let mut fgb = FgbDriver::from_path("places.fgb").expect("provide a places.fgb fixture to exercise FgbDriver::geometry_type()");
+	println!("places.fgb holds {:?} geometries", fgb.geometry_type());
+
+	// smoke-test nearest-neighbor iteration, fed by an origin parsed from a
+	// "lat,long" string the way a user-facing geo sort expression would supply
+	// one; expect() instead of `if let Ok` so a regression fails loudly
+	let origin = parse_origin("40.7128, -74.0060").expect("hardcoded origin string is well-formed");
+	let mut nearest = fgb.sort_by_distance(origin).expect("sort_by_distance against places.fgb");
+	while nearest.forward()? {
+		if let Some(geometry) = nearest.get_field_geometry("geometry")? {
+			println!("nearest place is a {}", geometry_kind(&geometry));
+		}
+	}
+
 	Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// a bare-bones FeatureReader for unit-testing trait default impls and the
+	// #[derive(AutoStruct)] macro without needing a real GPKG/FGB/GeoJSON file
+	struct MockFeatureReader {
+		i64_fields: std::collections::HashMap<&'static str, i64>,
+		geometry_fields: std::collections::HashMap<&'static str, Geometry>,
+	}
+
+	impl FeatureReader for MockFeatureReader {
+		fn forward(&mut self) -> Result<bool, Box<dyn Error>> { Ok(true) }
+		fn get_field_i32(&self, field_name: &str) -> Result<Option<i32>, Box<dyn Error>> {
+			Ok(self.i64_fields.get(field_name).map(|v| *v as i32))
+		}
+		fn get_field_i64(&self, field_name: &str) -> Result<Option<i64>, Box<dyn Error>> {
+			Ok(self.i64_fields.get(field_name).copied())
+		}
+		fn get_field_point(&self, field_name: &str) -> Result<Option<Point>, Box<dyn Error>> {
+			match self.get_field_geometry(field_name)? {
+				Some(Geometry::Point(p)) => Ok(Some(p)),
+				Some(g) => Err(format!("expected a Point in field {:?}, got a {}", field_name, geometry_kind(&g)).into()),
+				None => Ok(None),
+			}
+		}
+		fn get_field_geometry(&self, field_name: &str) -> Result<Option<Geometry>, Box<dyn Error>> {
+			Ok(self.geometry_fields.get(field_name).cloned())
+		}
+	}
+
+	#[test]
+	fn autostruct_derive_generates_a_working_generate() {
+		let reader = MockFeatureReader {
+			i64_fields: std::collections::HashMap::from([("x", 7)]),
+			geometry_fields: std::collections::HashMap::from([("geometry", Geometry::Point(Point::new(-74.0060, 40.7128)))]),
+		};
+
+		let place = MyStruct::generate(&reader).unwrap();
+		assert_eq!(place.x, 7);
+		assert_eq!(place.geometry, Point::new(-74.0060, 40.7128));
+	}
+
+	#[test]
+	fn autostruct_derive_errors_on_a_missing_required_field() {
+		let reader = MockFeatureReader {
+			i64_fields: std::collections::HashMap::new(),
+			geometry_fields: std::collections::HashMap::from([("geometry", Geometry::Point(Point::new(0.0, 0.0)))]),
+		};
+
+		assert!(MyStruct::generate(&reader).is_err());
+	}
+
+	#[test]
+	fn get_field_linestring_reads_a_linestring_field() {
+		let line = LineString::from(vec![(0.0, 0.0), (1.0, 1.0)]);
+		let reader = MockFeatureReader {
+			i64_fields: std::collections::HashMap::new(),
+			geometry_fields: std::collections::HashMap::from([("route", Geometry::LineString(line.clone()))]),
+		};
+		assert_eq!(reader.get_field_linestring("route").unwrap(), Some(line));
+	}
+
+	#[test]
+	fn get_field_polygon_errors_with_a_descriptive_message_on_a_type_mismatch() {
+		let reader = MockFeatureReader {
+			i64_fields: std::collections::HashMap::new(),
+			geometry_fields: std::collections::HashMap::from([("geometry", Geometry::Point(Point::new(0.0, 0.0)))]),
+		};
+		let err = reader.get_field_polygon("geometry").unwrap_err();
+		assert!(err.to_string().contains("expected a Polygon"));
+		assert!(err.to_string().contains("Point"));
+	}
+
+	#[test]
+	fn http_fgb_driver_only_opens_http_urls_ending_in_fgb() {
+		assert!(HttpFgbDriver::can_open("https://example.org/places.fgb"));
+		assert!(HttpFgbDriver::can_open("http://example.org/places.fgb"));
+		assert!(!HttpFgbDriver::can_open("places.fgb"));
+		assert!(!HttpFgbDriver::can_open("https://example.org/places.gpkg"));
+	}
+
+	#[test]
+	fn path_regexp_splits_file_path_from_layer_name() {
+		let re = Regex::new(PATH_REGEXP).unwrap();
+
+		let caps = re.captures("places.gpkg:cities").unwrap();
+		assert_eq!(&caps["file_path"], "places.gpkg");
+		assert_eq!(caps.name("layer_name").unwrap().as_str(), "cities");
+
+		let caps = re.captures("places.gpkg").unwrap();
+		assert_eq!(&caps["file_path"], "places.gpkg");
+		assert!(caps.name("layer_name").is_none());
+
+		assert!(re.captures("places").is_none());
+	}
+
+	#[test]
+	fn geojson_writer_round_trips_a_point_feature() {
+		let path = std::env::temp_dir().join(format!("autostruct_test_{}.geojson", std::process::id()));
+		let path_str = path.to_str().unwrap();
+
+		let mut writer = GeoJsonWriter::to_path(path_str).unwrap();
+		writer.write_field_i64("x", 42).unwrap();
+		writer.write_geometry(&Point::new(-74.0060, 40.7128)).unwrap();
+		writer.end_feature().unwrap();
+		writer.finish().unwrap();
+
+		let mut driver = GeoJsonDriver::from_path(path_str).unwrap();
+		let mut layer = driver.iter().unwrap();
+		assert!(layer.forward().unwrap());
+		assert_eq!(layer.get_field_i64("x").unwrap(), Some(42));
+		assert_eq!(layer.get_field_point("geometry").unwrap(), Some(Point::new(-74.0060, 40.7128)));
+		assert!(!layer.forward().unwrap());
+
+		std::fs::remove_file(path).unwrap();
+	}
+
+	#[test]
+	fn select_bbox_tests_non_point_geometries_by_their_anchor() {
+		// regression test: select_bbox used to only test `Geometry::Point`
+		// features against the rectangle and silently pass everything else
+		// through unfiltered
+		let path = std::env::temp_dir().join(format!("autostruct_test_bbox_{}.geojson", std::process::id()));
+		std::fs::write(&path, r#"{"type":"FeatureCollection","features":[
+			{"type":"Feature","properties":{"x":1},"geometry":{"type":"LineString","coordinates":[[-74.1,40.6],[-73.9,40.8]]}},
+			{"type":"Feature","properties":{"x":2},"geometry":{"type":"LineString","coordinates":[[10.0,10.0],[10.1,10.1]]}}
+		]}"#).unwrap();
+
+		let mut driver = GeoJsonDriver::from_path(path.to_str().unwrap()).unwrap();
+		let mut layer = driver.select_bbox(-75.0, 40.0, -73.0, 41.0).unwrap();
+
+		assert!(layer.forward().unwrap());
+		assert_eq!(layer.get_field_i64("x").unwrap(), Some(1));
+		assert!(!layer.forward().unwrap());
+
+		std::fs::remove_file(path).unwrap();
+	}
+
+	#[test]
+	fn parse_origin_splits_lat_long_and_trims_whitespace() {
+		assert_eq!(parse_origin("40.7128, -74.0060").unwrap(), Point::new(-74.0060, 40.7128));
+		assert_eq!(parse_origin("0,0").unwrap(), Point::new(0.0, 0.0));
+	}
+
+	#[test]
+	fn parse_origin_errors_on_malformed_input() {
+		assert!(parse_origin("not a point").is_err());
+		assert!(parse_origin("abc,1.0").is_err());
+		assert!(parse_origin("1.0,abc").is_err());
+	}
+
+	#[test]
+	fn gpkg_by_distance_panics_instead_of_looking_missing_on_a_non_integer_field() {
+		// regression test: a ByDistance record used to only ever capture
+		// Integer/Integer64 properties, silently dropping every other field
+		// type, so reading one back looked indistinguishable from "absent"
+		let mut properties = std::collections::HashMap::new();
+		properties.insert("name".to_string(), FieldValue::StringValue("Downtown".to_string()));
+		let record = GpkgRecord {
+			properties,
+			geometry: Geometry::Point(Point::new(0.0, 0.0)),
+			distance: 0.0,
+		};
+		let layer = GpkgLayer::ByDistance(vec![record], Some(0));
+
+		let err = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| layer.get_field_i64("name")));
+		assert!(err.is_err(), "reading a non-integer field through ByDistance should panic, the same way Streaming does");
+		assert_eq!(layer.get_field_i64("missing").unwrap(), None);
+	}
+
+	#[test]
+	fn fgb_by_distance_errors_instead_of_looking_missing_on_a_non_integer_field() {
+		let record = FgbRecord {
+			properties: std::collections::HashMap::new(),
+			other_fields: std::collections::HashSet::from(["name".to_string()]),
+			geometry: Geometry::Point(Point::new(0.0, 0.0)),
+			distance: 0.0,
+		};
+		let layer = FgbFeatureReader::ByDistance(vec![record], Some(0));
+
+		assert!(layer.get_field_i64("name").is_err());
+		assert_eq!(layer.get_field_i64("missing").unwrap(), None);
+	}
+}